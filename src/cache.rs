@@ -0,0 +1,55 @@
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::PathBuf;
+use std::{env, fs};
+
+fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join("askrepo")
+}
+
+/// Derives a cache key from the assembled prompt, model name, and the
+/// backend endpoint (provider plus whichever of base URL / Vertex
+/// project+location identifies it) — together the inputs that determine
+/// the API response, so switching backends can't serve a stale answer
+/// cached under another one.
+pub fn cache_key(prompt: &str, model: &str, endpoint: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prompt.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(model.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(endpoint.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Returns the cached response for `key`, if one was written by a previous run.
+pub fn read(key: &str) -> Option<String> {
+    fs::read_to_string(cache_dir().join(key)).ok()
+}
+
+/// Writes `content` to the cache under `key`, creating the cache directory
+/// if needed.
+pub fn write(key: &str, content: &str) -> io::Result<()> {
+    let dir = cache_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(key), content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key_is_stable_and_input_sensitive() {
+        let a = cache_key("prompt", "model-a", "endpoint-a");
+        let b = cache_key("prompt", "model-a", "endpoint-a");
+        let c = cache_key("prompt", "model-b", "endpoint-a");
+        let d = cache_key("prompt", "model-a", "endpoint-b");
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+    }
+}
@@ -1,7 +1,10 @@
 use ignore::WalkBuilder;
 use memchr::memchr;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::cmp::min;
+use std::collections::HashMap;
+use std::time::SystemTime;
 use std::{fs, io};
 
 const MAX_SCAN_SIZE: usize = 1024;
@@ -12,12 +15,76 @@ const MAGIC_NUMBERS: &[&[u8]] = &[
     b"GIF89a",            // GIF
 ];
 
+// mime_guess only classifies `text/*` as textual; these additional MIME
+// types are textual too (JSON, XML, JS, SVG) but are reported as
+// `application/*` or `image/*`, so they need an explicit allow-list.
+const TEXTUAL_MIME_TYPES: &[&str] = &[
+    "application/json",
+    "application/xml",
+    "application/javascript",
+    "image/svg+xml",
+];
+
+// Binary extensions mime_guess has no entry for at all (it falls back to
+// `None` rather than guessing wrong), so they need to be special-cased.
+const UNKNOWN_BINARY_EXTENSIONS: &[&str] = &["sqlite", "sqlite3"];
+
+// Source extensions mime_guess maps to a non-textual MIME entirely unrelated
+// to their use as source code (e.g. `.ts` guesses `video/mp2t`, the MPEG
+// transport stream type). These must win over mime_guess's verdict, not just
+// patch its essence string, and must stay in sync with `language_hint`'s
+// extension table below.
+const TEXTUAL_SOURCE_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts"];
+
 fn is_binary_file_by_extension(file: &str) -> bool {
-    let binary_extensions = ["jpg", "jpeg", "png", "gif", "bmp", "exe", "dll"];
-    file.split('.')
-        .last()
-        .map(|extension| binary_extensions.contains(&extension.to_lowercase().as_str()))
-        .unwrap_or(false)
+    let ext = file.rsplit('.').next().map(|ext| ext.to_lowercase());
+    if let Some(ext) = &ext {
+        if TEXTUAL_SOURCE_EXTENSIONS.contains(&ext.as_str()) {
+            return false;
+        }
+    }
+
+    match mime_guess::from_path(file).first() {
+        Some(mime) => {
+            mime.type_() != mime::TEXT && !TEXTUAL_MIME_TYPES.contains(&mime.essence_str())
+        }
+        None => ext
+            .map(|ext| UNKNOWN_BINARY_EXTENSIONS.contains(&ext.as_str()))
+            .unwrap_or(false),
+    }
+}
+
+/// Returns a fenced-code-block language hint for `file`'s extension, or
+/// an empty string when the extension maps to none (or is unknown).
+fn language_hint(file: &str) -> &'static str {
+    match file.rsplit('.').next().map(|ext| ext.to_lowercase()) {
+        Some(ext) => match ext.as_str() {
+            "rs" => "rust",
+            "py" => "python",
+            "js" | "mjs" | "cjs" => "javascript",
+            "ts" | "mts" | "cts" => "typescript",
+            "tsx" => "tsx",
+            "jsx" => "jsx",
+            "go" => "go",
+            "java" => "java",
+            "c" => "c",
+            "h" => "c",
+            "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+            "rb" => "ruby",
+            "php" => "php",
+            "sh" | "bash" => "bash",
+            "json" => "json",
+            "yaml" | "yml" => "yaml",
+            "toml" => "toml",
+            "md" => "markdown",
+            "html" | "htm" => "html",
+            "css" => "css",
+            "xml" => "xml",
+            "sql" => "sql",
+            _ => "",
+        },
+        None => "",
+    }
 }
 
 fn is_binary_file_by_content(file: &str) -> bool {
@@ -60,6 +127,30 @@ pub fn get_tracked_files(base_path: &str) -> io::Result<Vec<String>> {
     Ok(files)
 }
 
+/// Takes a mtime snapshot of every tracked file under `base_path`, for
+/// cheaply detecting whether a watched tree has changed since the last poll.
+pub fn snapshot_mtimes(base_path: &str) -> io::Result<HashMap<String, SystemTime>> {
+    let files = get_tracked_files(base_path)?;
+    let mut snapshot = HashMap::new();
+    for file in files {
+        if let Ok(modified) = fs::metadata(&file).and_then(|meta| meta.modified()) {
+            snapshot.insert(file, modified);
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Hashes the content of the tracked files under `base_path` as assembled by
+/// `get_files_content` (binary and unreadable files are skipped), so
+/// callers can skip re-querying the API when a save didn't actually change
+/// anything (e.g. touching a file without editing it).
+pub fn content_digest(base_path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let content = get_files_content(base_path)?;
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 pub fn get_files_content(base_path: &str) -> Result<String, Box<dyn std::error::Error>> {
     let files = get_tracked_files(base_path)?;
     let mut result = Vec::new();
@@ -71,7 +162,8 @@ pub fn get_files_content(base_path: &str) -> Result<String, Box<dyn std::error::
 
         match fs::read_to_string(&file) {
             Ok(content) => {
-                let escaped_content = json!(content).to_string();
+                let fenced_content = format!("```{}\n{}\n```", language_hint(&file), content);
+                let escaped_content = json!(fenced_content).to_string();
                 let double_escaped_content = json!(escaped_content).to_string();
                 let trimmed_content = &double_escaped_content[1..double_escaped_content.len() - 1];
                 result.push(format!("{}\t{}", file, trimmed_content));
@@ -127,6 +219,45 @@ mod tests {
         cleanup_test_dir(test_dir);
     }
 
+    #[test]
+    fn test_is_binary_file_by_extension_covers_more_than_images() {
+        assert!(is_binary_file_by_extension("archive.zip"));
+        assert!(is_binary_file_by_extension("module.wasm"));
+        assert!(is_binary_file_by_extension("font.woff"));
+        assert!(is_binary_file_by_extension("db.sqlite"));
+
+        assert!(!is_binary_file_by_extension("data.json"));
+        assert!(!is_binary_file_by_extension("script.js"));
+        assert!(!is_binary_file_by_extension("icon.svg"));
+        assert!(!is_binary_file_by_extension("main.rs"));
+        assert!(!is_binary_file_by_extension("component.ts"));
+        assert!(!is_binary_file_by_extension("component.tsx"));
+    }
+
+    #[test]
+    fn test_language_hint() {
+        assert_eq!(language_hint("src/main.rs"), "rust");
+        assert_eq!(language_hint("script.py"), "python");
+        assert_eq!(language_hint("README"), "");
+    }
+
+    #[test]
+    fn test_content_digest_changes_with_content() {
+        let test_dir = "test_digest_dir";
+        setup_test_dir(test_dir).expect("Failed to create test directory");
+
+        let test_file_path = format!("{}/test.txt", test_dir);
+        fs::write(&test_file_path, "version one").expect("Failed to write test file");
+        let first = content_digest(test_dir).expect("Failed to hash tracked files");
+
+        fs::write(&test_file_path, "version two").expect("Failed to write test file");
+        let second = content_digest(test_dir).expect("Failed to hash tracked files");
+
+        assert_ne!(first, second);
+
+        cleanup_test_dir(test_dir);
+    }
+
     #[test]
     fn test_get_tracked_files() {
         let test_dir = "test_tracked_dir";
@@ -1,3 +1,5 @@
+use crate::vertex_auth;
+use clap::ValueEnum;
 use reqwest::Client;
 use std::error::Error;
 use serde_json::{json, Value};
@@ -5,29 +7,107 @@ use futures_util::StreamExt;
 use tokio::sync::mpsc;
 use tokio_stream;
 
+/// Which backend `get_google_api_data` talks to: the OpenAI-compatible
+/// Gemini endpoint with a static API key, or Vertex AI with ADC.
+#[derive(Clone, ValueEnum)]
+pub enum Provider {
+    OpenaiCompat,
+    #[value(name = "vertexai")]
+    VertexAi,
+}
+
+/// Vertex AI connection details, only required when `provider` is `VertexAi`.
+pub struct VertexConfig<'a> {
+    pub project_id: &'a str,
+    pub location: &'a str,
+    pub adc_file: &'a str,
+}
+
+/// Shape of the streamed chunks `parse_api_response` should expect: the
+/// OpenAI-compatible `choices[0].delta` shim, or the native Gemini
+/// `candidates[0].content.parts[]` protocol used by `streamGenerateContent`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ResponseFormat {
+    OpenAiDelta,
+    GeminiNative,
+}
+
+impl Provider {
+    fn response_format(&self) -> ResponseFormat {
+        match self {
+            Provider::OpenaiCompat => ResponseFormat::OpenAiDelta,
+            Provider::VertexAi => ResponseFormat::GeminiNative,
+        }
+    }
+}
+
+/// Reshapes OpenAI-style `{role, content}` messages into Gemini's
+/// `{role, parts:[{text}]}` `contents` shape, mapping the `assistant` role
+/// to Gemini's `model`.
+fn to_gemini_contents(messages: &[Value]) -> Vec<Value> {
+    messages
+        .iter()
+        .map(|message| {
+            let role = match message["role"].as_str() {
+                Some("assistant") => "model",
+                Some(role) => role,
+                None => "user",
+            };
+            let text = message["content"].as_str().unwrap_or_default();
+            json!({ "role": role, "parts": [{ "text": text }] })
+        })
+        .collect()
+}
+
 pub async fn get_google_api_data(
     api_key: &str,
     messages: Vec<Value>,
     model: &str,
     stream: bool,
     base_url: &str,
+    provider: &Provider,
+    vertex: Option<&VertexConfig<'_>>,
 ) -> Result<impl futures_util::Stream<Item = String>, Box<dyn Error>> {
     let client = Client::builder()
         .http1_title_case_headers()
         .build()
         .unwrap();
-    let body = json!({
-        "model": model,
-        "messages": messages,
-        "stream": stream
-    });
+
+    let (url, auth_token, body) = match provider {
+        Provider::OpenaiCompat => {
+            let body = json!({
+                "model": model,
+                "messages": messages,
+                "stream": stream
+            });
+            (base_url.to_string(), api_key.to_string(), body)
+        }
+        Provider::VertexAi => {
+            let vertex = vertex.ok_or("--project-id, --location, and --adc-file are required for the vertexai provider")?;
+            let access_token = vertex_auth::get_access_token(vertex.adc_file).await?;
+            // `?alt=sse` makes the endpoint emit one complete JSON object per
+            // `data: ` line instead of a single pretty-printed array streamed
+            // across arbitrary byte boundaries, which `parse_gemini_native_response`
+            // can't safely reassemble chunk-by-chunk.
+            let url = format!(
+                "https://{location}-aiplatform.googleapis.com/v1/projects/{project_id}/locations/{location}/publishers/google/models/{model}:streamGenerateContent?alt=sse",
+                location = vertex.location,
+                project_id = vertex.project_id,
+                model = model
+            );
+            let body = json!({ "contents": to_gemini_contents(&messages) });
+            (url, access_token, body)
+        }
+    };
 
     let request = client
-        .post(base_url)
+        .post(url)
         .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Authorization", format!("Bearer {}", auth_token))
         .json(&body);
 
+    let response_format = provider.response_format();
+
     if stream {
         let response = request.send().await?;
         let byte_stream = response.bytes_stream();
@@ -39,7 +119,7 @@ pub async fn get_google_api_data(
                 match chunk {
                     Ok(c) => {
                         if let Ok(s) = String::from_utf8(c.to_vec()) {
-                            let content = parse_google_api_response(&s);
+                            let content = parse_api_response(&s, response_format);
                             if !content.is_empty() {
                                 let _ = tx.send(content).await;
                             }
@@ -65,23 +145,31 @@ pub async fn get_google_api_data(
     }
 }
 
-pub fn parse_google_api_response(data: &str) -> String {
+/// Dispatches a raw chunk of streamed bytes to the parser matching `format`.
+pub fn parse_api_response(data: &str, format: ResponseFormat) -> String {
+    match format {
+        ResponseFormat::OpenAiDelta => parse_openai_delta_response(data),
+        ResponseFormat::GeminiNative => parse_gemini_native_response(data),
+    }
+}
+
+pub fn parse_openai_delta_response(data: &str) -> String {
     let mut result = String::new();
-    
+
     // Split the input into lines and process each line
     for line in data.lines() {
         let line = line.trim();
-            
+
         // Skip empty lines
         if line.is_empty() {
             continue;
         }
-        
+
         // Check for DONE signal
         if line == "data: [DONE]" {
             continue;
         }
-        
+
         // Process lines starting with "data: "
         if line.starts_with("data: ") {
             if let Some(json_str) = line.strip_prefix("data: ").map(|s| s.trim()) {
@@ -119,43 +207,122 @@ pub fn parse_google_api_response(data: &str) -> String {
     result
 }
 
+/// Parses the native Vertex/Gemini `streamGenerateContent` shape. The
+/// endpoint emits a top-level JSON array of response objects (optionally as
+/// SSE `data: ` lines); text lives at `candidates[0].content.parts[].text`
+/// and a safety block is reported via `promptFeedback.blockReason`.
+pub fn parse_gemini_native_response(data: &str) -> String {
+    let mut result = String::new();
+
+    for v in extract_gemini_json_objects(data) {
+        if let Some(block_reason) = v["promptFeedback"]["blockReason"].as_str() {
+            result.push_str(&format!("[blockReason: {}]", block_reason));
+            continue;
+        }
+
+        let Some(candidate) = v["candidates"].get(0) else {
+            continue;
+        };
+
+        if let Some(parts) = candidate["content"]["parts"].as_array() {
+            for part in parts {
+                if let Some(text) = part["text"].as_str() {
+                    result.push_str(text);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Splits an SSE-or-bare-array streaming payload into the individual JSON
+/// values it contains, stripping `data: ` prefixes so each entry can be
+/// parsed on its own. Parsing is delegated to `serde_json`'s own streaming
+/// reader rather than hand-rolled brace counting, so braces inside string
+/// values (e.g. a `text` part containing `"if (x) {"`) don't split values early.
+fn extract_gemini_json_objects(data: &str) -> Vec<Value> {
+    let trimmed = data.trim();
+
+    let body = if trimmed.lines().any(|line| line.trim_start().starts_with("data: ")) {
+        trimmed
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("data: "))
+            .collect::<Vec<_>>()
+            .join("")
+    } else {
+        trimmed.to_string()
+    };
+
+    serde_json::Deserializer::from_str(&body)
+        .into_iter::<Value>()
+        .filter_map(Result::ok)
+        .flat_map(|v| match v {
+            Value::Array(items) => items,
+            other => vec![other],
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tokio::runtime::Runtime;
 
     #[test]
-    fn test_parse_google_api_response() {
+    fn test_parse_openai_delta_response() {
         let response = r#"data: {"id":"chatcmpl-123","object":"chat.completion.chunk","choices":[{"index":0,"delta":{"content":"こんにちは"},"finish_reason":null}]}"#;
-        let result = parse_google_api_response(response);
+        let result = parse_openai_delta_response(response);
         assert_eq!(result, "こんにちは");
     }
 
     #[test]
-    fn test_parse_google_api_response_raw() {
+    fn test_parse_openai_delta_response_raw() {
         let response = "This is a raw response";
-        let result = parse_google_api_response(response);
+        let result = parse_openai_delta_response(response);
         assert_eq!(result, "This is a raw response");
     }
 
     #[test]
-    fn test_parse_google_api_response_empty() {
+    fn test_parse_openai_delta_response_empty() {
         let json_data = "";
-        let result = parse_google_api_response(json_data);
+        let result = parse_openai_delta_response(json_data);
         assert_eq!(result, "");
     }
 
     #[test]
-    fn test_parse_google_api_response_multiple_chunks() {
+    fn test_parse_openai_delta_response_multiple_chunks() {
         let response = r#"data: {"id":"chatcmpl-1","object":"chat.completion.chunk","choices":[{"index":0,"delta":{"content":"He"},"finish_reason":null}]}
 data: {"id":"chatcmpl-2","object":"chat.completion.chunk","choices":[{"index":0,"delta":{"content":"ll"},"finish_reason":null}]}
 data: {"id":"chatcmpl-3","object":"chat.completion.chunk","choices":[{"index":0,"delta":{"content":"o"},"finish_reason":null}]}
 data: {"id":"chatcmpl-4","object":"chat.completion.chunk","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}
 data: [DONE]"#;
-        let result = parse_google_api_response(response);
+        let result = parse_openai_delta_response(response);
         assert_eq!(result, "Hello");
     }
 
+    #[test]
+    fn test_parse_gemini_native_response_array() {
+        let response = r#"[{"candidates":[{"content":{"role":"model","parts":[{"text":"Hello"}]}}]},
+{"candidates":[{"content":{"role":"model","parts":[{"text":", world"}]},"finishReason":"STOP"}]}]"#;
+        let result = parse_gemini_native_response(response);
+        assert_eq!(result, "Hello, world");
+    }
+
+    #[test]
+    fn test_parse_gemini_native_response_block_reason() {
+        let response = r#"{"promptFeedback":{"blockReason":"SAFETY"}}"#;
+        let result = parse_gemini_native_response(response);
+        assert_eq!(result, "[blockReason: SAFETY]");
+    }
+
+    #[test]
+    fn test_parse_gemini_native_response_sse() {
+        let response = "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Hi\"}]}}]}\n\n";
+        let result = parse_gemini_native_response(response);
+        assert_eq!(result, "Hi");
+    }
+
     #[test]
     fn test_get_google_api_data() {
         let rt = Runtime::new().unwrap();
@@ -172,7 +339,16 @@ data: [DONE]"#;
                 })
             ];
             let model = "test_model";
-            let result = get_google_api_data(api_key, messages, model, false, "https://api.openai.com/v1/chat/completions").await;
+            let result = get_google_api_data(
+                api_key,
+                messages,
+                model,
+                false,
+                "https://api.openai.com/v1/chat/completions",
+                &Provider::OpenaiCompat,
+                None,
+            )
+            .await;
             assert!(result.is_ok());
         });
     }
@@ -1,7 +1,10 @@
+mod cache;
 mod file_utils;
 mod google_api;
+mod vertex_auth;
 
 use clap::Parser;
+use google_api::Provider;
 use log::{error, info};
 use std::env;
 use std::env::current_dir;
@@ -32,6 +35,126 @@ struct Args {
 
     #[arg(long, default_value = "true")]
     stream: bool,
+
+    #[arg(long, value_enum, default_value = "openai-compat")]
+    provider: Provider,
+
+    #[arg(long)]
+    project_id: Option<String>,
+
+    #[arg(long, default_value = "us-central1")]
+    location: String,
+
+    #[arg(long)]
+    adc_file: Option<String>,
+
+    #[arg(long)]
+    no_cache: bool,
+
+    #[arg(long)]
+    refresh_cache: bool,
+
+    #[arg(long)]
+    watch: bool,
+}
+
+struct Session {
+    path: String,
+    instruction: String,
+    model: String,
+    api_key: String,
+    base_url: String,
+    provider: Provider,
+    project_id: Option<String>,
+    location: String,
+    adc_file: Option<String>,
+    stream: bool,
+    no_cache: bool,
+    refresh_cache: bool,
+}
+
+/// Assembles the prompt from `session.path`'s tracked files, serves it from
+/// the response cache if possible, and otherwise queries the API, streaming
+/// and caching the answer.
+async fn ask_once(session: &Session) {
+    let files_content = match file_utils::get_files_content(&session.path) {
+        Ok(content) => content,
+        Err(e) => {
+            error!("Failed to get files content: {}", e);
+            return;
+        }
+    };
+
+    let prompt = format!(
+        "The following is information read from a list of source codes.\n\nFiles:\n{}\n\nQuestion:\n{}\n\nPlease answer the question by referencing the specific filenames and source code from the files provided above.",
+        files_content, session.instruction
+    );
+
+    let endpoint = match session.provider {
+        Provider::OpenaiCompat => session.base_url.clone(),
+        Provider::VertexAi => format!(
+            "vertexai:{}/{}",
+            session.project_id.as_deref().unwrap_or(""),
+            session.location
+        ),
+    };
+    let cache_key = cache::cache_key(&prompt, &session.model, &endpoint);
+
+    if !session.no_cache && !session.refresh_cache {
+        if let Some(cached) = cache::read(&cache_key) {
+            info!("Serving cached response for key {}", cache_key);
+            print!("{}", cached);
+            std::io::stdout().flush().unwrap();
+            return;
+        }
+    }
+
+    let vertex_config = match session.provider {
+        Provider::VertexAi => match (session.project_id.as_deref(), session.adc_file.as_deref()) {
+            (Some(project_id), Some(adc_file)) => Some(google_api::VertexConfig {
+                project_id,
+                location: &session.location,
+                adc_file,
+            }),
+            _ => {
+                error!("--project-id and --adc-file are required for the vertexai provider");
+                return;
+            }
+        },
+        Provider::OpenaiCompat => None,
+    };
+
+    let messages = vec![serde_json::json!({
+        "role": "user",
+        "content": prompt
+    })];
+    match google_api::get_google_api_data(
+        &session.api_key,
+        messages,
+        &session.model,
+        session.stream,
+        &session.base_url,
+        &session.provider,
+        vertex_config.as_ref(),
+    )
+    .await
+    {
+        Ok(mut stream) => {
+            let mut response = String::new();
+            while let Some(text) = stream.next().await {
+                info!("Extracted text:\n{}", text);
+                print!("{}", text);
+                std::io::stdout().flush().unwrap();
+                response.push_str(&text);
+            }
+            if !session.no_cache && !response.is_empty() {
+                if let Err(e) = cache::write(&cache_key, &response) {
+                    error!("Failed to write response cache: {}", e);
+                }
+            }
+        }
+        Err(e) => error!("Error fetching API data: {}", e),
+    }
 }
 
 #[tokio::main]
@@ -56,15 +179,15 @@ async fn main() {
             }
         },
     };
-    let instruction = args.prompt;
-    let model = args.model.unwrap();
-    let api_key = args.api_key.unwrap_or_else(|| {
-        env::var("GOOGLE_API_KEY").unwrap_or_else(|_| {
-            error!("GOOGLE_API_KEY environment variable not set");
-            exit(1);
-        })
-    });
-    let base_url = args.base_url;
+    let api_key = match &args.provider {
+        Provider::OpenaiCompat => args.api_key.clone().unwrap_or_else(|| {
+            env::var("GOOGLE_API_KEY").unwrap_or_else(|_| {
+                error!("GOOGLE_API_KEY environment variable not set");
+                exit(1);
+            })
+        }),
+        Provider::VertexAi => String::new(),
+    };
 
     if Command::new("git").arg("--version").output().await.is_err() {
         error!("Git is not installed or not found in the execution path.");
@@ -76,31 +199,48 @@ async fn main() {
         exit(1);
     }
 
-    let files_content = match file_utils::get_files_content(&path) {
-        Ok(content) => content,
-        Err(e) => {
-            error!("Failed to get files content: {}", e);
-            return;
-        }
+    let watch = args.watch;
+    let session = Session {
+        path: path.clone(),
+        instruction: args.prompt,
+        model: args.model.unwrap(),
+        api_key,
+        base_url: args.base_url,
+        provider: args.provider,
+        project_id: args.project_id,
+        location: args.location,
+        adc_file: args.adc_file,
+        stream: args.stream,
+        no_cache: args.no_cache,
+        refresh_cache: args.refresh_cache,
     };
 
-    let prompt = format!(
-        "The following is information read from a list of source codes.\n\nFiles:\n{}\n\nQuestion:\n{}\n\nPlease answer the question by referencing the specific filenames and source code from the files provided above.",
-        files_content, instruction
-    );
+    ask_once(&session).await;
 
-    let messages = vec![serde_json::json!({
-        "role": "user",
-        "content": prompt
-    })];
-    match google_api::get_google_api_data(&api_key, messages, &model, args.stream, &base_url).await {
-        Ok(mut stream) => {
-            while let Some(text) = stream.next().await {
-                info!("Extracted text:\n{}", text);
-                print!("{}", text);
-                std::io::stdout().flush().unwrap();
+    if !watch {
+        return;
+    }
+
+    let mut last_digest = file_utils::content_digest(&path).ok();
+    let mut last_mtimes = file_utils::snapshot_mtimes(&path).unwrap_or_default();
+
+    loop {
+        let mtimes = file_utils::snapshot_mtimes(&path).unwrap_or_default();
+        if mtimes != last_mtimes {
+            last_mtimes = mtimes;
+
+            match file_utils::content_digest(&path) {
+                Ok(digest) if Some(&digest) != last_digest.as_ref() => {
+                    last_digest = Some(digest);
+                    print!("\x1B[2J\x1B[1;1H");
+                    std::io::stdout().flush().unwrap();
+                    ask_once(&session).await;
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to hash tracked files: {}", e),
             }
         }
-        Err(e) => error!("Error fetching API data: {}", e),
+
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,105 @@
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+#[derive(Deserialize)]
+struct ServiceAccount {
+    client_email: String,
+    private_key: String,
+}
+
+#[derive(Serialize)]
+struct Claims {
+    iss: String,
+    scope: String,
+    aud: String,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: u64,
+}
+
+fn token_cache() -> &'static Mutex<Option<CachedToken>> {
+    static CACHE: OnceLock<Mutex<Option<CachedToken>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Returns a cached Vertex AI access token, minting a fresh one via a
+/// service-account JWT assertion when the cache is empty or about to expire.
+pub async fn get_access_token(adc_file: &str) -> Result<String, Box<dyn Error>> {
+    if let Some(token) = token_cache().lock().unwrap().as_ref() {
+        if token.expires_at > now() + 60 {
+            return Ok(token.access_token.clone());
+        }
+    }
+
+    let data = fs::read_to_string(adc_file)?;
+    let service_account: ServiceAccount = serde_json::from_str(&data)?;
+
+    let iat = now();
+    let exp = iat + 3600;
+    let claims = Claims {
+        iss: service_account.client_email,
+        scope: SCOPE.to_string(),
+        aud: TOKEN_URL.to_string(),
+        iat,
+        exp,
+    };
+
+    let key = EncodingKey::from_rsa_pem(service_account.private_key.as_bytes())?;
+    let jwt = encode(&Header::new(Algorithm::RS256), &claims, &key)?;
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(TOKEN_URL)
+        .form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", jwt.as_str()),
+        ])
+        .send()
+        .await?;
+
+    let token_response: TokenResponse = response.json().await?;
+
+    let access_token = token_response.access_token.clone();
+    *token_cache().lock().unwrap() = Some(CachedToken {
+        access_token: token_response.access_token,
+        expires_at: iat + token_response.expires_in,
+    });
+
+    Ok(access_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_now_is_monotonic_increasing() {
+        let a = now();
+        let b = now();
+        assert!(b >= a);
+    }
+}